@@ -4,7 +4,11 @@ use rand::Rng;
 use std::fmt::Display;
 use std::fmt::Formatter;
 use rand::seq::index::sample;
-use simsimd::sparse_dot_product;
+use simsimd::{
+    sparse_cosine, sparse_dot_product, sparse_dot_product_batch,
+    sparse_dot_product_batch_threaded, sparse_dot_product_compressed, sparse_jaccard,
+    sparse_sqeuclidean, CompressedSparseVector,
+};
 //use half::bf16 as hbf16;
 #[derive(Clone, Debug)]
 struct SparseVector {
@@ -12,6 +16,7 @@ struct SparseVector {
     values: Vec<f32>,
 }
 impl SparseVector {
+    #[allow(dead_code)]
     fn from_dense(dense_vec: &[f32]) -> Self {
         if dense_vec.len() >= u16::MAX as usize {
             panic!("Dense vector is too large to convert to sparse vector");
@@ -156,7 +161,242 @@ fn bench_dot_products(c: &mut Criterion) {
         }
     }
     neon_group.finish();
+
+    // Create benchmark group for the compressed (delta + nibble-packed) path
+    let mut compressed_group = c.benchmark_group("compressed_dot_product");
+    for &first_len in first_lens.iter() {
+        for &second_len in second_lens.iter() {
+            for &ratio in intersection_ratios.iter() {
+                let intersection_size = (ratio * second_len as f32).ceil() as usize;
+                let params = format!("{}x{}@{}", first_len, second_len, ratio);
+
+                compressed_group.bench_with_input(
+                    BenchmarkId::new("compressed", params),
+                    &(first_len, second_len, intersection_size),
+                    |b, &(f_len, s_len, i_size)| {
+                        b.iter_with_setup(
+                            || {
+                                let (first_vector, second_vector) =
+                                    generate_intersecting_vectors(f_len, s_len, i_size);
+                                let first_indices_u32: Vec<u32> = first_vector
+                                    .indices
+                                    .iter()
+                                    .map(|&idx| idx as u32)
+                                    .collect();
+                                let second_indices_u32: Vec<u32> = second_vector
+                                    .indices
+                                    .iter()
+                                    .map(|&idx| idx as u32)
+                                    .collect();
+                                (
+                                    CompressedSparseVector::encode(&first_indices_u32),
+                                    first_vector.values,
+                                    CompressedSparseVector::encode(&second_indices_u32),
+                                    second_vector.values,
+                                )
+                            },
+                            |(first_compressed, first_values, second_compressed, second_values)| {
+                                let (similar_items, _dot_product) = sparse_dot_product_compressed(
+                                    &first_compressed,
+                                    first_values.as_slice(),
+                                    &second_compressed,
+                                    second_values.as_slice(),
+                                );
+                                black_box(similar_items)
+                            }
+                        );
+                    }
+                );
+            }
+        }
+    }
+    compressed_group.finish();
+}
+
+fn bench_metrics(c: &mut Criterion) {
+    // Same size/ratio grid as `bench_dot_products`, one group per metric.
+    let first_lens = [66, 129, 513, 1025, 2049];
+    let second_lens = [9, 17, 33];
+    let intersection_ratios = [0.1, 0.5, 0.9];
+
+    let mut cosine_group = c.benchmark_group("sparse_cosine");
+    for &first_len in first_lens.iter() {
+        for &second_len in second_lens.iter() {
+            for &ratio in intersection_ratios.iter() {
+                let intersection_size = (ratio * second_len as f32).ceil() as usize;
+                let params = format!("{}x{}@{}", first_len, second_len, ratio);
+
+                cosine_group.bench_with_input(
+                    BenchmarkId::new("cosine", params),
+                    &(first_len, second_len, intersection_size),
+                    |b, &(f_len, s_len, i_size)| {
+                        b.iter_with_setup(
+                            || generate_intersecting_vectors(f_len, s_len, i_size),
+                            |(first_vector, second_vector)| {
+                                let similarity = sparse_cosine(
+                                    first_vector.indices.as_slice(),
+                                    second_vector.indices.as_slice(),
+                                    first_vector.values.as_slice(),
+                                    second_vector.values.as_slice(),
+                                );
+                                black_box(similarity)
+                            }
+                        );
+                    }
+                );
+            }
+        }
+    }
+    cosine_group.finish();
+
+    let mut jaccard_group = c.benchmark_group("sparse_jaccard");
+    for &first_len in first_lens.iter() {
+        for &second_len in second_lens.iter() {
+            for &ratio in intersection_ratios.iter() {
+                let intersection_size = (ratio * second_len as f32).ceil() as usize;
+                let params = format!("{}x{}@{}", first_len, second_len, ratio);
+
+                jaccard_group.bench_with_input(
+                    BenchmarkId::new("jaccard", params),
+                    &(first_len, second_len, intersection_size),
+                    |b, &(f_len, s_len, i_size)| {
+                        b.iter_with_setup(
+                            || generate_intersecting_vectors(f_len, s_len, i_size),
+                            |(first_vector, second_vector)| {
+                                let similarity = sparse_jaccard(
+                                    first_vector.indices.as_slice(),
+                                    second_vector.indices.as_slice(),
+                                );
+                                black_box(similarity)
+                            }
+                        );
+                    }
+                );
+            }
+        }
+    }
+    jaccard_group.finish();
+
+    let mut sqeuclidean_group = c.benchmark_group("sparse_sqeuclidean");
+    for &first_len in first_lens.iter() {
+        for &second_len in second_lens.iter() {
+            for &ratio in intersection_ratios.iter() {
+                let intersection_size = (ratio * second_len as f32).ceil() as usize;
+                let params = format!("{}x{}@{}", first_len, second_len, ratio);
+
+                sqeuclidean_group.bench_with_input(
+                    BenchmarkId::new("sqeuclidean", params),
+                    &(first_len, second_len, intersection_size),
+                    |b, &(f_len, s_len, i_size)| {
+                        b.iter_with_setup(
+                            || generate_intersecting_vectors(f_len, s_len, i_size),
+                            |(first_vector, second_vector)| {
+                                let distance = sparse_sqeuclidean(
+                                    first_vector.indices.as_slice(),
+                                    second_vector.indices.as_slice(),
+                                    first_vector.values.as_slice(),
+                                    second_vector.values.as_slice(),
+                                );
+                                black_box(distance)
+                            }
+                        );
+                    }
+                );
+            }
+        }
+    }
+    sqeuclidean_group.finish();
+}
+
+fn bench_batch(c: &mut Criterion) {
+    // One query scored against a corpus of increasing size, to show the
+    // amortization win of scoring the whole corpus in one call instead of
+    // one `sparse_dot_product` call per entry.
+    let query_len = 513;
+    let corpus_vector_len = 17;
+    let intersection_size = 8;
+    let corpus_sizes = [16, 64, 256, 1024];
+
+    let build_corpus = |size: usize| -> (SparseVector, Vec<SparseVector>) {
+        let (query, _) = generate_intersecting_vectors(query_len, corpus_vector_len, intersection_size);
+        let corpus = (0..size)
+            .map(|_| generate_intersecting_vectors(query_len, corpus_vector_len, intersection_size).1)
+            .collect();
+        (query, corpus)
+    };
+
+    let mut batch_group = c.benchmark_group("sparse_dot_product_batch");
+    for &corpus_size in corpus_sizes.iter() {
+        batch_group.bench_with_input(
+            BenchmarkId::new("one_call_per_entry", corpus_size),
+            &corpus_size,
+            |b, &size| {
+                b.iter_with_setup(
+                    || build_corpus(size),
+                    |(query, corpus)| {
+                        let scores: Vec<(u64, f64)> = corpus
+                            .iter()
+                            .map(|entry| {
+                                sparse_dot_product(
+                                    query.indices.as_slice(),
+                                    entry.indices.as_slice(),
+                                    query.values.as_slice(),
+                                    entry.values.as_slice(),
+                                )
+                            })
+                            .collect();
+                        black_box(scores);
+                    }
+                );
+            }
+        );
+
+        batch_group.bench_with_input(
+            BenchmarkId::new("batch_single_threaded", corpus_size),
+            &corpus_size,
+            |b, &size| {
+                b.iter_with_setup(
+                    || build_corpus(size),
+                    |(query, corpus)| {
+                        let corpus_refs: Vec<(&[u16], &[f32])> = corpus
+                            .iter()
+                            .map(|entry| (entry.indices.as_slice(), entry.values.as_slice()))
+                            .collect();
+                        let scores = sparse_dot_product_batch(
+                            query.indices.as_slice(),
+                            query.values.as_slice(),
+                            corpus_refs.as_slice(),
+                        );
+                        black_box(scores);
+                    }
+                );
+            }
+        );
+
+        batch_group.bench_with_input(
+            BenchmarkId::new("batch_threaded", corpus_size),
+            &corpus_size,
+            |b, &size| {
+                b.iter_with_setup(
+                    || build_corpus(size),
+                    |(query, corpus)| {
+                        let corpus_refs: Vec<(&[u16], &[f32])> = corpus
+                            .iter()
+                            .map(|entry| (entry.indices.as_slice(), entry.values.as_slice()))
+                            .collect();
+                        let scores = sparse_dot_product_batch_threaded(
+                            query.indices.as_slice(),
+                            query.values.as_slice(),
+                            corpus_refs.as_slice(),
+                        );
+                        black_box(scores);
+                    }
+                );
+            }
+        );
+    }
+    batch_group.finish();
 }
 
-criterion_group!(benches, bench_dot_products);
+criterion_group!(benches, bench_dot_products, bench_metrics, bench_batch);
 criterion_main!(benches);
\ No newline at end of file
@@ -0,0 +1,271 @@
+//! Delta + nibble-packed storage for `u32` sparse vector indices.
+//!
+//! Indices are stored as successive deltas of the sorted sequence, packed
+//! eight at a time: a leading byte marks which of the group's eight lanes
+//! overflow a nibble (`delta >= 0xF`), four bytes hold two 4-bit deltas
+//! each, and any overflowing lanes get a trailing 4-byte little-endian
+//! escape. [`sparse_dot_product_compressed`] merges two of these directly,
+//! decoding one group at a time instead of expanding either side into a
+//! `Vec<u32>` up front.
+
+use std::cmp::Ordering;
+
+/// Deltas per nibble-packed group.
+const GROUP_SIZE: usize = 8;
+/// Nibble value reserved to mean "see the trailing 4-byte escape".
+const ESCAPE_NIBBLE: u8 = 0xF;
+
+/// A `u32`-indexed sparse vector's indices, delta-encoded and nibble-packed.
+///
+/// Holds only the index stream; values stay as a plain `&[f32]` alongside
+/// it, since they're already as compact as they'll get.
+#[derive(Clone, Debug)]
+pub struct CompressedSparseVector {
+    len: usize,
+    bytes: Vec<u8>,
+}
+
+impl CompressedSparseVector {
+    /// Encodes a sorted, deduplicated index slice.
+    pub fn encode(indices: &[u32]) -> Self {
+        let mut bytes = Vec::with_capacity(indices.len());
+        let mut prev = 0u32;
+
+        for group in indices.chunks(GROUP_SIZE) {
+            let mut deltas = [0u32; GROUP_SIZE];
+            for (lane, &idx) in group.iter().enumerate() {
+                deltas[lane] = idx - prev;
+                prev = idx;
+            }
+
+            let mut escapes = 0u8;
+            for (lane, &delta) in deltas.iter().enumerate().take(group.len()) {
+                if delta >= ESCAPE_NIBBLE as u32 {
+                    escapes |= 1 << lane;
+                }
+            }
+            bytes.push(escapes);
+
+            for pair in 0..GROUP_SIZE / 2 {
+                let lo = nibble_for(deltas[pair * 2], escapes, pair * 2);
+                let hi = nibble_for(deltas[pair * 2 + 1], escapes, pair * 2 + 1);
+                bytes.push((hi << 4) | lo);
+            }
+
+            for (lane, &delta) in deltas.iter().enumerate().take(group.len()) {
+                if escapes & (1 << lane) != 0 {
+                    bytes.extend_from_slice(&delta.to_le_bytes());
+                }
+            }
+        }
+
+        CompressedSparseVector {
+            len: indices.len(),
+            bytes,
+        }
+    }
+
+    /// Number of indices encoded.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether this encodes an empty index set.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// A fresh streaming decoder positioned before the first index.
+    fn decoder(&self) -> CompressedSparseDecoder<'_> {
+        CompressedSparseDecoder {
+            bytes: &self.bytes,
+            pos: 0,
+            remaining: self.len,
+            prev: 0,
+            group: [0; GROUP_SIZE],
+            group_len: 0,
+            group_pos: 0,
+        }
+    }
+
+    /// Fully expands back to a plain `Vec<u32>`.
+    ///
+    /// Defeats the point of compressing in the first place; use
+    /// [`sparse_dot_product_compressed`] to merge without expanding, and
+    /// reach for this only for debugging or round-trip tests.
+    pub fn decode(&self) -> Vec<u32> {
+        let mut decoder = self.decoder();
+        let mut out = Vec::with_capacity(self.len);
+        while let Some(idx) = decoder.next() {
+            out.push(idx);
+        }
+        out
+    }
+}
+
+/// The nibble stored for `delta` at `lane`: the delta itself if it fits in
+/// four bits, or [`ESCAPE_NIBBLE`] if `escapes` marks it as overflowed.
+fn nibble_for(delta: u32, escapes: u8, lane: usize) -> u8 {
+    if escapes & (1 << lane) != 0 {
+        ESCAPE_NIBBLE
+    } else {
+        delta as u8
+    }
+}
+
+/// Streaming decoder that yields one absolute index at a time, decoding a
+/// fresh [`GROUP_SIZE`]-wide group only once the previous one is exhausted.
+struct CompressedSparseDecoder<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    remaining: usize,
+    prev: u32,
+    group: [u32; GROUP_SIZE],
+    group_len: usize,
+    group_pos: usize,
+}
+
+impl<'a> CompressedSparseDecoder<'a> {
+    fn fill_group(&mut self) {
+        let count = self.remaining.min(GROUP_SIZE);
+        let escapes = self.bytes[self.pos];
+        self.pos += 1;
+        let nibble_bytes = &self.bytes[self.pos..self.pos + GROUP_SIZE / 2];
+        self.pos += GROUP_SIZE / 2;
+
+        let mut deltas = [0u32; GROUP_SIZE];
+        for lane in 0..count {
+            if escapes & (1 << lane) != 0 {
+                let escape =
+                    u32::from_le_bytes(self.bytes[self.pos..self.pos + 4].try_into().unwrap());
+                self.pos += 4;
+                deltas[lane] = escape;
+            } else {
+                let byte = nibble_bytes[lane / 2];
+                deltas[lane] = u32::from(if lane % 2 == 0 {
+                    byte & 0x0F
+                } else {
+                    byte >> 4
+                });
+            }
+        }
+
+        let mut prev = self.prev;
+        for (lane, &delta) in deltas.iter().enumerate().take(count) {
+            prev += delta;
+            self.group[lane] = prev;
+        }
+        self.prev = prev;
+
+        self.group_len = count;
+        self.group_pos = 0;
+        self.remaining -= count;
+    }
+
+    fn next(&mut self) -> Option<u32> {
+        if self.group_pos == self.group_len {
+            if self.remaining == 0 {
+                return None;
+            }
+            self.fill_group();
+        }
+        let idx = self.group[self.group_pos];
+        self.group_pos += 1;
+        Some(idx)
+    }
+}
+
+/// Merge-joins two compressed sparse vectors and returns `(matches, dot)`,
+/// decoding each index stream one group at a time instead of expanding
+/// either side into a `Vec<u32>`.
+pub fn sparse_dot_product_compressed(
+    indices_a: &CompressedSparseVector,
+    values_a: &[f32],
+    indices_b: &CompressedSparseVector,
+    values_b: &[f32],
+) -> (u64, f64) {
+    let mut decoder_a = indices_a.decoder();
+    let mut decoder_b = indices_b.decoder();
+    let mut i = 0usize;
+    let mut j = 0usize;
+    let mut cursor_a = decoder_a.next();
+    let mut cursor_b = decoder_b.next();
+
+    let mut matches: u64 = 0;
+    let mut result = 0.0;
+
+    while let (Some(idx_a), Some(idx_b)) = (cursor_a, cursor_b) {
+        match idx_a.cmp(&idx_b) {
+            Ordering::Equal => {
+                matches += 1;
+                result += f64::from(values_a[i] * values_b[j]);
+                i += 1;
+                j += 1;
+                cursor_a = decoder_a.next();
+                cursor_b = decoder_b.next();
+            }
+            Ordering::Less => {
+                i += 1;
+                cursor_a = decoder_a.next();
+            }
+            Ordering::Greater => {
+                j += 1;
+                cursor_b = decoder_b.next();
+            }
+        }
+    }
+
+    (matches, result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_empty() {
+        let compressed = CompressedSparseVector::encode(&[]);
+        assert!(compressed.is_empty());
+        assert_eq!(compressed.decode(), Vec::<u32>::new());
+    }
+
+    #[test]
+    fn round_trips_within_a_single_group() {
+        let indices = [1u32, 2, 5, 9];
+        let compressed = CompressedSparseVector::encode(&indices);
+        assert_eq!(compressed.len(), indices.len());
+        assert_eq!(compressed.decode(), indices);
+    }
+
+    #[test]
+    fn round_trips_across_group_boundaries() {
+        let indices: Vec<u32> = (0..40).map(|i| i * 3).collect();
+        let compressed = CompressedSparseVector::encode(&indices);
+        assert_eq!(compressed.decode(), indices);
+    }
+
+    #[test]
+    fn round_trips_large_deltas_needing_escapes() {
+        let indices = [10u32, 2_000, 2_001, 70_000, 70_100];
+        let compressed = CompressedSparseVector::encode(&indices);
+        assert_eq!(compressed.decode(), indices);
+    }
+
+    #[test]
+    fn dot_product_matches_the_plain_merge() {
+        let indices_a = [1u32, 4, 9, 2_000, 2_050];
+        let values_a = [1.0f32, 2.0, 3.0, 4.0, 5.0];
+        let indices_b = [4u32, 9, 2_050, 70_000];
+        let values_b = [10.0f32, 20.0, 30.0, 40.0];
+
+        let compressed_a = CompressedSparseVector::encode(&indices_a);
+        let compressed_b = CompressedSparseVector::encode(&indices_b);
+
+        let (matches, dot) =
+            sparse_dot_product_compressed(&compressed_a, &values_a, &compressed_b, &values_b);
+
+        // 4 matches 4 (20), 9 matches 9 (60), 2050 matches 2050 (150).
+        assert_eq!(matches, 3);
+        assert!((dot - (2.0 * 10.0 + 3.0 * 20.0 + 5.0 * 30.0)).abs() < 1e-9);
+    }
+}
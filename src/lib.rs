@@ -0,0 +1,16 @@
+//! Similarity kernels for sparse vectors, with NEON-accelerated
+//! paths on `aarch64` and scalar fallbacks everywhere else.
+
+pub mod compressed;
+pub mod sparse;
+
+pub use compressed::{sparse_dot_product_compressed, CompressedSparseVector};
+pub use sparse::{
+    sparse_cosine, sparse_cosine_u32, sparse_cosine_u64, sparse_dot_product,
+    sparse_dot_product_batch, sparse_dot_product_batch_threaded,
+    sparse_dot_product_batch_threaded_u32, sparse_dot_product_batch_threaded_u64,
+    sparse_dot_product_batch_u32, sparse_dot_product_batch_u64, sparse_dot_product_bitmap,
+    sparse_dot_product_bitmap_u32, sparse_dot_product_bitmap_u64, sparse_dot_product_u32,
+    sparse_dot_product_u64, sparse_jaccard, sparse_jaccard_u32, sparse_jaccard_u64,
+    sparse_sqeuclidean, sparse_sqeuclidean_u32, sparse_sqeuclidean_u64, SparseIndex, SparseVector,
+};
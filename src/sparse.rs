@@ -0,0 +1,1367 @@
+//! Sparse vector dot products over sorted, deduplicated index arrays.
+//!
+//! Each `sparse_dot_product*` entry point intersects two `(indices, values)`
+//! pairs: indices are assumed strictly increasing, so a single pass through
+//! both arrays finds every matching index, similarly to merging two sorted
+//! runs. Lopsided pairs are galloped instead, and small, dense index domains
+//! go through a bitmap intersection.
+
+use std::cmp::Ordering;
+
+/// A sparse vector index.
+///
+/// Implemented for `u16`, `u32` and `u64` so callers can pick the narrowest
+/// width that fits their vocabulary size instead of being capped at 65,536
+/// dimensions.
+pub trait SparseIndex: Ord + Copy {
+    /// Widens the index to `u64`, e.g. to address a bitmap word/lane.
+    fn to_u64(self) -> u64;
+}
+
+impl SparseIndex for u16 {
+    fn to_u64(self) -> u64 {
+        self as u64
+    }
+}
+impl SparseIndex for u32 {
+    fn to_u64(self) -> u64 {
+        self as u64
+    }
+}
+impl SparseIndex for u64 {
+    fn to_u64(self) -> u64 {
+        self
+    }
+}
+
+/// A sparse vector stored as parallel sorted-index / value arrays.
+///
+/// `Idx` controls how large the dense dimension can be: `u16` caps out at
+/// 65,536 dimensions, `u32` and `u64` trade a little memory for headroom.
+#[derive(Clone, Debug)]
+pub struct SparseVector<Idx = u16> {
+    pub indices: Vec<Idx>,
+    pub values: Vec<f32>,
+}
+
+impl<Idx> SparseVector<Idx>
+where
+    Idx: SparseIndex + TryFrom<usize>,
+{
+    /// Builds a sparse vector from a dense one, keeping only non-zero entries.
+    ///
+    /// Panics if a non-zero entry sits at a position that `Idx` cannot
+    /// represent; trailing zeros beyond that range are skipped without panicking.
+    pub fn from_dense(dense_vec: &[f32]) -> Self {
+        let mut indices = Vec::new();
+        let mut values = Vec::new();
+
+        for (idx, &value) in dense_vec.iter().enumerate() {
+            if value != 0.0 {
+                let idx = Idx::try_from(idx)
+                    .unwrap_or_else(|_| panic!("dense vector is too large for this index width"));
+                indices.push(idx);
+                values.push(value);
+            }
+        }
+
+        SparseVector { indices, values }
+    }
+}
+
+/// Scalar merge-join reference implementation, also used as the fallback on
+/// targets without a NEON-accelerated path.
+fn merge_dot_product<Idx: SparseIndex>(
+    indices_a: &[Idx],
+    indices_b: &[Idx],
+    values_a: &[f32],
+    values_b: &[f32],
+) -> (u64, f64) {
+    let mut result = 0.0;
+    let mut i = 0;
+    let mut j = 0;
+    let mut matches: u64 = 0;
+
+    while i < indices_a.len() && j < indices_b.len() {
+        match indices_a[i].cmp(&indices_b[j]) {
+            Ordering::Equal => {
+                matches += 1;
+                result += f64::from(values_a[i] * values_b[j]);
+                i += 1;
+                j += 1;
+            }
+            Ordering::Less => i += 1,
+            Ordering::Greater => j += 1,
+        }
+    }
+
+    (matches, result)
+}
+
+/// Ratio of the longer array's length to the shorter one at or above which
+/// galloping search beats a linear merge.
+const GALLOP_RATIO: usize = 8;
+
+/// Whether one of `len_a`/`len_b` is at least [`GALLOP_RATIO`] times the
+/// other, i.e. the pair is lopsided enough for galloping to pay off.
+fn should_gallop(len_a: usize, len_b: usize) -> bool {
+    (len_a > 0 && len_b >= GALLOP_RATIO * len_a) || (len_b > 0 && len_a >= GALLOP_RATIO * len_b)
+}
+
+/// Finds `target` in `indices[start..]` by exponential search followed by a
+/// binary search within the bracketing window, assuming `indices` is sorted
+/// ascending. Mirrors the standard library's `binary_search`: `Ok` gives the
+/// match position, `Err` gives where `target` would be inserted.
+fn gallop_search<Idx: SparseIndex>(
+    indices: &[Idx],
+    start: usize,
+    target: Idx,
+) -> Result<usize, usize> {
+    let len = indices.len();
+    if start >= len {
+        return Err(start);
+    }
+    if indices[start] >= target {
+        return indices[start..]
+            .binary_search(&target)
+            .map(|pos| pos + start)
+            .map_err(|pos| pos + start);
+    }
+
+    let mut prev = start;
+    let mut step = 1usize;
+    let mut idx = start + step;
+    while idx < len && indices[idx] < target {
+        prev = idx;
+        step *= 2;
+        idx = start + step;
+    }
+    let hi = (idx + 1).min(len);
+
+    indices[prev..hi]
+        .binary_search(&target)
+        .map(|pos| pos + prev)
+        .map_err(|pos| pos + prev)
+}
+
+/// Merge-joins a short sparse vector against a much longer one by galloping
+/// the short side's indices through the long side with [`gallop_search`],
+/// resuming each search from the previous landing position.
+fn gallop_dot_product<Idx: SparseIndex>(
+    short_indices: &[Idx],
+    short_values: &[f32],
+    long_indices: &[Idx],
+    long_values: &[f32],
+) -> (u64, f64) {
+    let mut matches: u64 = 0;
+    let mut result = 0.0;
+    let mut cursor = 0usize;
+
+    for (k, &target) in short_indices.iter().enumerate() {
+        if cursor >= long_indices.len() {
+            break;
+        }
+        match gallop_search(long_indices, cursor, target) {
+            Ok(pos) => {
+                matches += 1;
+                result += f64::from(short_values[k] * long_values[pos]);
+                cursor = pos + 1;
+            }
+            Err(pos) => cursor = pos,
+        }
+    }
+
+    (matches, result)
+}
+
+/// Merge-joins two sparse vectors, galloping whichever side is shorter
+/// through the other.
+fn gallop_merge<Idx: SparseIndex>(
+    indices_a: &[Idx],
+    indices_b: &[Idx],
+    values_a: &[f32],
+    values_b: &[f32],
+) -> (u64, f64) {
+    if indices_a.len() <= indices_b.len() {
+        gallop_dot_product(indices_a, values_a, indices_b, values_b)
+    } else {
+        gallop_dot_product(indices_b, values_b, indices_a, values_a)
+    }
+}
+
+/// Index domain above which the bitmap path is skipped: two 65,536-bit
+/// bitmaps (one per side) are still a cheap 8 KiB each, but larger domains
+/// make the dense value buffers (one `f32` per index) too wasteful.
+const BITMAP_MAX_SPAN: u64 = 65_536;
+
+/// Combined density, `(len_a + len_b) / span`, at or above which a bitmap AND
+/// beats a merge: below this the arrays are sparse enough that most bitmap
+/// words are zero and the merge's early-out on short arrays wins instead.
+const BITMAP_MIN_DENSITY: f64 = 0.05;
+
+/// Index span covered by both arrays, i.e. `max(last_a, last_b) + 1`. Both
+/// arrays are sorted ascending, so this is just their last elements.
+///
+/// Saturates at `u64::MAX` instead of overflowing when the highest index is
+/// itself `u64::MAX`; [`should_bitmap`] rejects spans above
+/// [`BITMAP_MAX_SPAN`] anyway, so a saturated span just reads as "too big
+/// for the bitmap path" rather than wrapping to a bogus small one.
+fn domain_span<Idx: SparseIndex>(indices_a: &[Idx], indices_b: &[Idx]) -> u64 {
+    let max_a = indices_a.last().map_or(0, |&i| i.to_u64());
+    let max_b = indices_b.last().map_or(0, |&i| i.to_u64());
+    max_a.max(max_b).saturating_add(1)
+}
+
+/// Whether a bitmap intersection is expected to beat a merge for this pair:
+/// a small enough index domain and a high enough combined density.
+fn should_bitmap(len_a: usize, len_b: usize, span: u64) -> bool {
+    if span == 0 || span > BITMAP_MAX_SPAN {
+        return false;
+    }
+    let density = (len_a + len_b) as f64 / span as f64;
+    density >= BITMAP_MIN_DENSITY
+}
+
+/// Intersects two sparse vectors by materializing each into a word-packed
+/// `Vec<u64>` bitmap (bit `i` of word `idx >> 6` set to `1 << (idx & 63)`),
+/// ANDing the bitmaps word-by-word, and mapping surviving set bits back to
+/// their values through a dense per-domain buffer.
+///
+/// Intended for small, dense index domains; see [`should_bitmap`].
+fn bitmap_dot_product<Idx: SparseIndex>(
+    indices_a: &[Idx],
+    values_a: &[f32],
+    indices_b: &[Idx],
+    values_b: &[f32],
+) -> (u64, f64) {
+    let span = domain_span(indices_a, indices_b) as usize;
+    let words = span.div_ceil(64);
+
+    let mut bitmap_a = vec![0u64; words];
+    let mut bitmap_b = vec![0u64; words];
+    let mut dense_a = vec![0f32; span];
+    let mut dense_b = vec![0f32; span];
+
+    for (&idx, &value) in indices_a.iter().zip(values_a) {
+        let idx = idx.to_u64() as usize;
+        bitmap_a[idx >> 6] |= 1u64 << (idx & 63);
+        dense_a[idx] = value;
+    }
+    for (&idx, &value) in indices_b.iter().zip(values_b) {
+        let idx = idx.to_u64() as usize;
+        bitmap_b[idx >> 6] |= 1u64 << (idx & 63);
+        dense_b[idx] = value;
+    }
+
+    let mut matches: u64 = 0;
+    let mut result = 0.0;
+    for (word, (&word_a, &word_b)) in bitmap_a.iter().zip(&bitmap_b).enumerate() {
+        let mut bits = word_a & word_b;
+        while bits != 0 {
+            let lane = bits.trailing_zeros() as usize;
+            let idx = word * 64 + lane;
+            matches += 1;
+            result += f64::from(dense_a[idx] * dense_b[idx]);
+            bits &= bits - 1;
+        }
+    }
+
+    (matches, result)
+}
+
+/// Scalar merge-join that only counts matches, for callers (like
+/// [`sparse_jaccard`]) that don't need the dot product itself.
+fn merge_match_count<Idx: SparseIndex>(indices_a: &[Idx], indices_b: &[Idx]) -> u64 {
+    let mut i = 0;
+    let mut j = 0;
+    let mut matches: u64 = 0;
+
+    while i < indices_a.len() && j < indices_b.len() {
+        match indices_a[i].cmp(&indices_b[j]) {
+            Ordering::Equal => {
+                matches += 1;
+                i += 1;
+                j += 1;
+            }
+            Ordering::Less => i += 1,
+            Ordering::Greater => j += 1,
+        }
+    }
+
+    matches
+}
+
+/// [`gallop_search`]-based match count for a short/long pair.
+fn gallop_match_count<Idx: SparseIndex>(short_indices: &[Idx], long_indices: &[Idx]) -> u64 {
+    let mut matches: u64 = 0;
+    let mut cursor = 0usize;
+
+    for &target in short_indices {
+        if cursor >= long_indices.len() {
+            break;
+        }
+        match gallop_search(long_indices, cursor, target) {
+            Ok(pos) => {
+                matches += 1;
+                cursor = pos + 1;
+            }
+            Err(pos) => cursor = pos,
+        }
+    }
+
+    matches
+}
+
+/// Counts matches between two sparse vectors, galloping whichever side is
+/// shorter through the other.
+fn gallop_match_count_merge<Idx: SparseIndex>(indices_a: &[Idx], indices_b: &[Idx]) -> u64 {
+    if indices_a.len() <= indices_b.len() {
+        gallop_match_count(indices_a, indices_b)
+    } else {
+        gallop_match_count(indices_b, indices_a)
+    }
+}
+
+/// Bitmap-based match count: AND the two bitmaps word-by-word and
+/// `count_ones` each surviving word, without materializing dense value
+/// buffers since the values themselves aren't needed.
+fn bitmap_match_count<Idx: SparseIndex>(indices_a: &[Idx], indices_b: &[Idx]) -> u64 {
+    let span = domain_span(indices_a, indices_b) as usize;
+    let words = span.div_ceil(64);
+
+    let mut bitmap_a = vec![0u64; words];
+    let mut bitmap_b = vec![0u64; words];
+    for &idx in indices_a {
+        let idx = idx.to_u64() as usize;
+        bitmap_a[idx >> 6] |= 1u64 << (idx & 63);
+    }
+    for &idx in indices_b {
+        let idx = idx.to_u64() as usize;
+        bitmap_b[idx >> 6] |= 1u64 << (idx & 63);
+    }
+
+    bitmap_a
+        .iter()
+        .zip(&bitmap_b)
+        .map(|(&a, &b)| (a & b).count_ones() as u64)
+        .sum()
+}
+
+/// Sums the squares of `values`, accumulating in NEON `f32` lanes and
+/// widening to `f64` only after the horizontal reduction.
+///
+/// Used by [`sparse_cosine`] to compute each side's L2 norm and by
+/// [`sparse_sqeuclidean`] via the `||a-b||^2 = ||a||^2 + ||b||^2 - 2(a*b)`
+/// identity, so both reuse the same NEON-accelerated dot product kernel for
+/// the cross term.
+#[cfg(target_arch = "aarch64")]
+fn sum_of_squares(values: &[f32]) -> f64 {
+    unsafe { neon::sum_of_squares_f32(values) }
+}
+
+/// Sums the squares of `values`, widening to `f64` as it accumulates.
+#[cfg(not(target_arch = "aarch64"))]
+fn sum_of_squares(values: &[f32]) -> f64 {
+    values.iter().map(|&v| f64::from(v) * f64::from(v)).sum()
+}
+
+#[cfg(target_arch = "aarch64")]
+mod neon {
+    use std::arch::aarch64::*;
+    use std::cmp::Ordering;
+
+    /// Block width processed per NEON bounds check.
+    pub const BLOCK_U16: usize = 8;
+    pub const BLOCK_U32: usize = 4;
+
+    /// Returns the `(min, max)` of an 8-lane `u16` block.
+    #[target_feature(enable = "neon")]
+    unsafe fn bounds_u16(block: &[u16]) -> (u16, u16) {
+        let v = vld1q_u16(block.as_ptr());
+        (vminvq_u16(v), vmaxvq_u16(v))
+    }
+
+    /// Returns the `(min, max)` of a 4-lane `u32` block.
+    #[target_feature(enable = "neon")]
+    unsafe fn bounds_u32(block: &[u32]) -> (u32, u32) {
+        let v = vld1q_u32(block.as_ptr());
+        (vminvq_u32(v), vmaxvq_u32(v))
+    }
+
+    /// Merge-joins two `u16`-indexed sparse vectors.
+    ///
+    /// Whole blocks of [`BLOCK_U16`] indices are skipped in one step whenever
+    /// their value ranges don't overlap (checked with `vminvq`/`vmaxvq`);
+    /// overlapping or ragged-tail blocks fall back to a scalar merge step.
+    #[target_feature(enable = "neon")]
+    pub unsafe fn merge_u16(
+        indices_a: &[u16],
+        indices_b: &[u16],
+        values_a: &[f32],
+        values_b: &[f32],
+    ) -> (u64, f64) {
+        let mut i = 0;
+        let mut j = 0;
+        let mut matches: u64 = 0;
+        let mut result = 0.0;
+
+        while i < indices_a.len() && j < indices_b.len() {
+            if i + BLOCK_U16 <= indices_a.len() && j + BLOCK_U16 <= indices_b.len() {
+                let (a_min, a_max) = bounds_u16(&indices_a[i..i + BLOCK_U16]);
+                let (b_min, b_max) = bounds_u16(&indices_b[j..j + BLOCK_U16]);
+                if a_max < b_min {
+                    i += BLOCK_U16;
+                    continue;
+                }
+                if b_max < a_min {
+                    j += BLOCK_U16;
+                    continue;
+                }
+            }
+
+            match indices_a[i].cmp(&indices_b[j]) {
+                Ordering::Equal => {
+                    matches += 1;
+                    result += f64::from(values_a[i] * values_b[j]);
+                    i += 1;
+                    j += 1;
+                }
+                Ordering::Less => i += 1,
+                Ordering::Greater => j += 1,
+            }
+        }
+
+        (matches, result)
+    }
+
+    /// `u32` counterpart of [`merge_u16`], blocked at [`BLOCK_U32`] lanes.
+    #[target_feature(enable = "neon")]
+    pub unsafe fn merge_u32(
+        indices_a: &[u32],
+        indices_b: &[u32],
+        values_a: &[f32],
+        values_b: &[f32],
+    ) -> (u64, f64) {
+        let mut i = 0;
+        let mut j = 0;
+        let mut matches: u64 = 0;
+        let mut result = 0.0;
+
+        while i < indices_a.len() && j < indices_b.len() {
+            if i + BLOCK_U32 <= indices_a.len() && j + BLOCK_U32 <= indices_b.len() {
+                let (a_min, a_max) = bounds_u32(&indices_a[i..i + BLOCK_U32]);
+                let (b_min, b_max) = bounds_u32(&indices_b[j..j + BLOCK_U32]);
+                if a_max < b_min {
+                    i += BLOCK_U32;
+                    continue;
+                }
+                if b_max < a_min {
+                    j += BLOCK_U32;
+                    continue;
+                }
+            }
+
+            match indices_a[i].cmp(&indices_b[j]) {
+                Ordering::Equal => {
+                    matches += 1;
+                    result += f64::from(values_a[i] * values_b[j]);
+                    i += 1;
+                    j += 1;
+                }
+                Ordering::Less => i += 1,
+                Ordering::Greater => j += 1,
+            }
+        }
+
+        (matches, result)
+    }
+
+    /// `u16` counterpart of [`merge_u16`] that only counts matches, used by
+    /// [`super::sparse_jaccard`].
+    #[target_feature(enable = "neon")]
+    pub unsafe fn count_matches_u16(indices_a: &[u16], indices_b: &[u16]) -> u64 {
+        let mut i = 0;
+        let mut j = 0;
+        let mut matches: u64 = 0;
+
+        while i < indices_a.len() && j < indices_b.len() {
+            if i + BLOCK_U16 <= indices_a.len() && j + BLOCK_U16 <= indices_b.len() {
+                let (a_min, a_max) = bounds_u16(&indices_a[i..i + BLOCK_U16]);
+                let (b_min, b_max) = bounds_u16(&indices_b[j..j + BLOCK_U16]);
+                if a_max < b_min {
+                    i += BLOCK_U16;
+                    continue;
+                }
+                if b_max < a_min {
+                    j += BLOCK_U16;
+                    continue;
+                }
+            }
+
+            match indices_a[i].cmp(&indices_b[j]) {
+                Ordering::Equal => {
+                    matches += 1;
+                    i += 1;
+                    j += 1;
+                }
+                Ordering::Less => i += 1,
+                Ordering::Greater => j += 1,
+            }
+        }
+
+        matches
+    }
+
+    /// `u32` counterpart of [`count_matches_u16`], blocked at [`BLOCK_U32`] lanes.
+    #[target_feature(enable = "neon")]
+    pub unsafe fn count_matches_u32(indices_a: &[u32], indices_b: &[u32]) -> u64 {
+        let mut i = 0;
+        let mut j = 0;
+        let mut matches: u64 = 0;
+
+        while i < indices_a.len() && j < indices_b.len() {
+            if i + BLOCK_U32 <= indices_a.len() && j + BLOCK_U32 <= indices_b.len() {
+                let (a_min, a_max) = bounds_u32(&indices_a[i..i + BLOCK_U32]);
+                let (b_min, b_max) = bounds_u32(&indices_b[j..j + BLOCK_U32]);
+                if a_max < b_min {
+                    i += BLOCK_U32;
+                    continue;
+                }
+                if b_max < a_min {
+                    j += BLOCK_U32;
+                    continue;
+                }
+            }
+
+            match indices_a[i].cmp(&indices_b[j]) {
+                Ordering::Equal => {
+                    matches += 1;
+                    i += 1;
+                    j += 1;
+                }
+                Ordering::Less => i += 1,
+                Ordering::Greater => j += 1,
+            }
+        }
+
+        matches
+    }
+
+    /// Sums the squares of `values` four lanes at a time with a fused
+    /// multiply-add, reducing with `vaddvq_f32` and handling any remainder
+    /// (`values.len() % 4`) in scalar.
+    #[target_feature(enable = "neon")]
+    pub unsafe fn sum_of_squares_f32(values: &[f32]) -> f64 {
+        let mut chunks = values.chunks_exact(4);
+        let mut acc = vdupq_n_f32(0.0);
+        for chunk in &mut chunks {
+            let v = vld1q_f32(chunk.as_ptr());
+            acc = vfmaq_f32(acc, v, v);
+        }
+
+        let mut total = f64::from(vaddvq_f32(acc));
+        for &v in chunks.remainder() {
+            total += f64::from(v) * f64::from(v);
+        }
+        total
+    }
+}
+
+/// Merge-joins two `u16`-indexed sparse vectors and returns `(matches, dot)`.
+///
+/// Lopsided pairs (see [`should_gallop`]) are galloped and small, dense index
+/// domains (see [`should_bitmap`]) go through the bitmap intersection; every
+/// other pair uses a NEON-accelerated block-skipping merge on `aarch64` and a
+/// scalar two-pointer merge elsewhere.
+#[cfg(target_arch = "aarch64")]
+pub fn sparse_dot_product(
+    indices_a: &[u16],
+    indices_b: &[u16],
+    values_a: &[f32],
+    values_b: &[f32],
+) -> (u64, f64) {
+    if should_gallop(indices_a.len(), indices_b.len()) {
+        return gallop_merge(indices_a, indices_b, values_a, values_b);
+    }
+    if should_bitmap(
+        indices_a.len(),
+        indices_b.len(),
+        domain_span(indices_a, indices_b),
+    ) {
+        return bitmap_dot_product(indices_a, values_a, indices_b, values_b);
+    }
+    unsafe { neon::merge_u16(indices_a, indices_b, values_a, values_b) }
+}
+
+/// Merge-joins two `u16`-indexed sparse vectors and returns `(matches, dot)`.
+#[cfg(not(target_arch = "aarch64"))]
+pub fn sparse_dot_product(
+    indices_a: &[u16],
+    indices_b: &[u16],
+    values_a: &[f32],
+    values_b: &[f32],
+) -> (u64, f64) {
+    if should_gallop(indices_a.len(), indices_b.len()) {
+        return gallop_merge(indices_a, indices_b, values_a, values_b);
+    }
+    if should_bitmap(
+        indices_a.len(),
+        indices_b.len(),
+        domain_span(indices_a, indices_b),
+    ) {
+        return bitmap_dot_product(indices_a, values_a, indices_b, values_b);
+    }
+    merge_dot_product(indices_a, indices_b, values_a, values_b)
+}
+
+/// Forces the bitmap intersection for two `u16`-indexed sparse vectors,
+/// bypassing the [`should_bitmap`] heuristic.
+///
+/// Use this when the caller already knows the index domain is small and
+/// dense; see [`bitmap_dot_product`] for the algorithm.
+pub fn sparse_dot_product_bitmap(
+    indices_a: &[u16],
+    indices_b: &[u16],
+    values_a: &[f32],
+    values_b: &[f32],
+) -> (u64, f64) {
+    bitmap_dot_product(indices_a, values_a, indices_b, values_b)
+}
+
+/// `u32`-indexed counterpart of [`sparse_dot_product`], for vocabularies
+/// that outgrow `u16`'s 65,536-dimension ceiling.
+#[cfg(target_arch = "aarch64")]
+pub fn sparse_dot_product_u32(
+    indices_a: &[u32],
+    indices_b: &[u32],
+    values_a: &[f32],
+    values_b: &[f32],
+) -> (u64, f64) {
+    if should_gallop(indices_a.len(), indices_b.len()) {
+        return gallop_merge(indices_a, indices_b, values_a, values_b);
+    }
+    if should_bitmap(
+        indices_a.len(),
+        indices_b.len(),
+        domain_span(indices_a, indices_b),
+    ) {
+        return bitmap_dot_product(indices_a, values_a, indices_b, values_b);
+    }
+    unsafe { neon::merge_u32(indices_a, indices_b, values_a, values_b) }
+}
+
+/// `u32`-indexed counterpart of [`sparse_dot_product`], for vocabularies
+/// that outgrow `u16`'s 65,536-dimension ceiling.
+#[cfg(not(target_arch = "aarch64"))]
+pub fn sparse_dot_product_u32(
+    indices_a: &[u32],
+    indices_b: &[u32],
+    values_a: &[f32],
+    values_b: &[f32],
+) -> (u64, f64) {
+    if should_gallop(indices_a.len(), indices_b.len()) {
+        return gallop_merge(indices_a, indices_b, values_a, values_b);
+    }
+    if should_bitmap(
+        indices_a.len(),
+        indices_b.len(),
+        domain_span(indices_a, indices_b),
+    ) {
+        return bitmap_dot_product(indices_a, values_a, indices_b, values_b);
+    }
+    merge_dot_product(indices_a, indices_b, values_a, values_b)
+}
+
+/// Forces the bitmap intersection for two `u32`-indexed sparse vectors,
+/// bypassing the [`should_bitmap`] heuristic; see [`sparse_dot_product_bitmap`].
+pub fn sparse_dot_product_bitmap_u32(
+    indices_a: &[u32],
+    indices_b: &[u32],
+    values_a: &[f32],
+    values_b: &[f32],
+) -> (u64, f64) {
+    bitmap_dot_product(indices_a, values_a, indices_b, values_b)
+}
+
+/// `u64`-indexed counterpart of [`sparse_dot_product`].
+///
+/// NEON has no horizontal min/max reduction over 64-bit lanes, so the
+/// block-skipping trick used for `u16`/`u32` doesn't carry over; this is
+/// the scalar merge on every target, galloping included.
+pub fn sparse_dot_product_u64(
+    indices_a: &[u64],
+    indices_b: &[u64],
+    values_a: &[f32],
+    values_b: &[f32],
+) -> (u64, f64) {
+    if should_gallop(indices_a.len(), indices_b.len()) {
+        return gallop_merge(indices_a, indices_b, values_a, values_b);
+    }
+    if should_bitmap(
+        indices_a.len(),
+        indices_b.len(),
+        domain_span(indices_a, indices_b),
+    ) {
+        return bitmap_dot_product(indices_a, values_a, indices_b, values_b);
+    }
+    merge_dot_product(indices_a, indices_b, values_a, values_b)
+}
+
+/// Forces the bitmap intersection for two `u64`-indexed sparse vectors,
+/// bypassing the [`should_bitmap`] heuristic; see [`sparse_dot_product_bitmap`].
+pub fn sparse_dot_product_bitmap_u64(
+    indices_a: &[u64],
+    indices_b: &[u64],
+    values_a: &[f32],
+    values_b: &[f32],
+) -> (u64, f64) {
+    bitmap_dot_product(indices_a, values_a, indices_b, values_b)
+}
+
+/// Cosine similarity of two `u16`-indexed sparse vectors: their dot product
+/// divided by the product of their L2 norms.
+///
+/// The dot product comes straight from [`sparse_dot_product`] (so this
+/// inherits its galloping/bitmap/NEON dispatch), and each norm is an
+/// independent [`sum_of_squares`] pass over that side's full `values` slice.
+/// Returns `0.0` if either vector is all-zero.
+pub fn sparse_cosine(
+    indices_a: &[u16],
+    indices_b: &[u16],
+    values_a: &[f32],
+    values_b: &[f32],
+) -> f64 {
+    let (_matches, dot) = sparse_dot_product(indices_a, indices_b, values_a, values_b);
+    cosine_from_dot(dot, values_a, values_b)
+}
+
+/// `u32`-indexed counterpart of [`sparse_cosine`].
+pub fn sparse_cosine_u32(
+    indices_a: &[u32],
+    indices_b: &[u32],
+    values_a: &[f32],
+    values_b: &[f32],
+) -> f64 {
+    let (_matches, dot) = sparse_dot_product_u32(indices_a, indices_b, values_a, values_b);
+    cosine_from_dot(dot, values_a, values_b)
+}
+
+/// `u64`-indexed counterpart of [`sparse_cosine`].
+pub fn sparse_cosine_u64(
+    indices_a: &[u64],
+    indices_b: &[u64],
+    values_a: &[f32],
+    values_b: &[f32],
+) -> f64 {
+    let (_matches, dot) = sparse_dot_product_u64(indices_a, indices_b, values_a, values_b);
+    cosine_from_dot(dot, values_a, values_b)
+}
+
+fn cosine_from_dot(dot: f64, values_a: &[f32], values_b: &[f32]) -> f64 {
+    let norm_a = sum_of_squares(values_a).sqrt();
+    let norm_b = sum_of_squares(values_b).sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
+
+/// Jaccard similarity of two `u16`-indexed index sets: `|A∩B| / |A∪B|`.
+///
+/// The union size follows from the match count and the two lengths
+/// (`|A∪B| = |A| + |B| - |A∩B|`), so this only needs a matches-only merge —
+/// the same galloping/bitmap/NEON dispatch as [`sparse_dot_product`], minus
+/// the value accumulation. Returns `0.0` if both sets are empty.
+pub fn sparse_jaccard(indices_a: &[u16], indices_b: &[u16]) -> f64 {
+    jaccard_from_matches(
+        indices_a.len(),
+        indices_b.len(),
+        matches_u16(indices_a, indices_b),
+    )
+}
+
+/// `u32`-indexed counterpart of [`sparse_jaccard`].
+pub fn sparse_jaccard_u32(indices_a: &[u32], indices_b: &[u32]) -> f64 {
+    jaccard_from_matches(
+        indices_a.len(),
+        indices_b.len(),
+        matches_u32(indices_a, indices_b),
+    )
+}
+
+/// `u64`-indexed counterpart of [`sparse_jaccard`].
+pub fn sparse_jaccard_u64(indices_a: &[u64], indices_b: &[u64]) -> f64 {
+    jaccard_from_matches(
+        indices_a.len(),
+        indices_b.len(),
+        matches_u64(indices_a, indices_b),
+    )
+}
+
+fn jaccard_from_matches(len_a: usize, len_b: usize, matches: u64) -> f64 {
+    let union = len_a as u64 + len_b as u64 - matches;
+    if union == 0 {
+        0.0
+    } else {
+        matches as f64 / union as f64
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+fn matches_u16(indices_a: &[u16], indices_b: &[u16]) -> u64 {
+    if should_gallop(indices_a.len(), indices_b.len()) {
+        return gallop_match_count_merge(indices_a, indices_b);
+    }
+    if should_bitmap(
+        indices_a.len(),
+        indices_b.len(),
+        domain_span(indices_a, indices_b),
+    ) {
+        return bitmap_match_count(indices_a, indices_b);
+    }
+    unsafe { neon::count_matches_u16(indices_a, indices_b) }
+}
+
+#[cfg(not(target_arch = "aarch64"))]
+fn matches_u16(indices_a: &[u16], indices_b: &[u16]) -> u64 {
+    if should_gallop(indices_a.len(), indices_b.len()) {
+        return gallop_match_count_merge(indices_a, indices_b);
+    }
+    if should_bitmap(
+        indices_a.len(),
+        indices_b.len(),
+        domain_span(indices_a, indices_b),
+    ) {
+        return bitmap_match_count(indices_a, indices_b);
+    }
+    merge_match_count(indices_a, indices_b)
+}
+
+#[cfg(target_arch = "aarch64")]
+fn matches_u32(indices_a: &[u32], indices_b: &[u32]) -> u64 {
+    if should_gallop(indices_a.len(), indices_b.len()) {
+        return gallop_match_count_merge(indices_a, indices_b);
+    }
+    if should_bitmap(
+        indices_a.len(),
+        indices_b.len(),
+        domain_span(indices_a, indices_b),
+    ) {
+        return bitmap_match_count(indices_a, indices_b);
+    }
+    unsafe { neon::count_matches_u32(indices_a, indices_b) }
+}
+
+#[cfg(not(target_arch = "aarch64"))]
+fn matches_u32(indices_a: &[u32], indices_b: &[u32]) -> u64 {
+    if should_gallop(indices_a.len(), indices_b.len()) {
+        return gallop_match_count_merge(indices_a, indices_b);
+    }
+    if should_bitmap(
+        indices_a.len(),
+        indices_b.len(),
+        domain_span(indices_a, indices_b),
+    ) {
+        return bitmap_match_count(indices_a, indices_b);
+    }
+    merge_match_count(indices_a, indices_b)
+}
+
+/// `u64`-indexed counterpart of [`matches_u16`]/[`matches_u32`].
+///
+/// No NEON variant: like [`sparse_dot_product_u64`], there's no
+/// horizontal min/max reduction over 64-bit lanes for the block-skipping
+/// merge to use, so this is the scalar merge on every target, galloping
+/// and bitmap dispatch included.
+fn matches_u64(indices_a: &[u64], indices_b: &[u64]) -> u64 {
+    if should_gallop(indices_a.len(), indices_b.len()) {
+        return gallop_match_count_merge(indices_a, indices_b);
+    }
+    if should_bitmap(
+        indices_a.len(),
+        indices_b.len(),
+        domain_span(indices_a, indices_b),
+    ) {
+        return bitmap_match_count(indices_a, indices_b);
+    }
+    merge_match_count(indices_a, indices_b)
+}
+
+/// Squared Euclidean distance between two `u16`-indexed sparse vectors over
+/// the union of their indices: matched positions contribute `(a_i-b_i)^2`,
+/// unmatched ones contribute the lone squared value.
+///
+/// Rather than re-merging to visit the union directly, this uses the
+/// identity `||a-b||^2 = ||a||^2 + ||b||^2 - 2(a·b)`, so it reuses the same
+/// dot product and [`sum_of_squares`] kernels as [`sparse_cosine`].
+pub fn sparse_sqeuclidean(
+    indices_a: &[u16],
+    indices_b: &[u16],
+    values_a: &[f32],
+    values_b: &[f32],
+) -> f64 {
+    let (_matches, dot) = sparse_dot_product(indices_a, indices_b, values_a, values_b);
+    sqeuclidean_from_dot(dot, values_a, values_b)
+}
+
+/// `u32`-indexed counterpart of [`sparse_sqeuclidean`].
+pub fn sparse_sqeuclidean_u32(
+    indices_a: &[u32],
+    indices_b: &[u32],
+    values_a: &[f32],
+    values_b: &[f32],
+) -> f64 {
+    let (_matches, dot) = sparse_dot_product_u32(indices_a, indices_b, values_a, values_b);
+    sqeuclidean_from_dot(dot, values_a, values_b)
+}
+
+/// `u64`-indexed counterpart of [`sparse_sqeuclidean`].
+pub fn sparse_sqeuclidean_u64(
+    indices_a: &[u64],
+    indices_b: &[u64],
+    values_a: &[f32],
+    values_b: &[f32],
+) -> f64 {
+    let (_matches, dot) = sparse_dot_product_u64(indices_a, indices_b, values_a, values_b);
+    sqeuclidean_from_dot(dot, values_a, values_b)
+}
+
+fn sqeuclidean_from_dot(dot: f64, values_a: &[f32], values_b: &[f32]) -> f64 {
+    sum_of_squares(values_a) + sum_of_squares(values_b) - 2.0 * dot
+}
+
+/// A width-specific dot-product entry point, threaded through the batch
+/// helpers below so they don't have to re-implement per-width dispatch.
+type DotFn<Idx> = fn(&[Idx], &[Idx], &[f32], &[f32]) -> (u64, f64);
+
+/// Scores `query` against every entry in `corpus` with `dot`, one call at a
+/// time. The query's indices and values stay in place across the whole
+/// sweep, so the only per-entry cost is the merge itself.
+fn batch_dot_product<Idx: SparseIndex>(
+    query_indices: &[Idx],
+    query_values: &[f32],
+    corpus: &[(&[Idx], &[f32])],
+    dot: DotFn<Idx>,
+) -> Vec<(u64, f64)> {
+    corpus
+        .iter()
+        .map(|&(indices, values)| dot(query_indices, indices, query_values, values))
+        .collect()
+}
+
+/// Same contract as [`batch_dot_product`], but splits `corpus` into one
+/// contiguous chunk per available core and scores each chunk on its own
+/// thread. Falls back to the single-threaded sweep when there's only one
+/// core to use or fewer than two corpus entries.
+fn batch_dot_product_threaded<Idx: SparseIndex + Sync>(
+    query_indices: &[Idx],
+    query_values: &[f32],
+    corpus: &[(&[Idx], &[f32])],
+    dot: DotFn<Idx>,
+) -> Vec<(u64, f64)> {
+    let thread_count = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+        .min(corpus.len());
+
+    if thread_count <= 1 {
+        return batch_dot_product(query_indices, query_values, corpus, dot);
+    }
+
+    let chunk_size = corpus.len().div_ceil(thread_count);
+    let mut results = vec![(0u64, 0.0f64); corpus.len()];
+
+    std::thread::scope(|scope| {
+        for (corpus_chunk, results_chunk) in corpus
+            .chunks(chunk_size)
+            .zip(results.chunks_mut(chunk_size))
+        {
+            scope.spawn(move || {
+                for (slot, &(indices, values)) in results_chunk.iter_mut().zip(corpus_chunk) {
+                    *slot = dot(query_indices, indices, query_values, values);
+                }
+            });
+        }
+    });
+
+    results
+}
+
+/// Scores a `u16`-indexed query against a whole corpus, reusing
+/// [`sparse_dot_product`]'s dispatch for each comparison.
+///
+/// Prefer this over calling [`sparse_dot_product`] in a loop: the query's
+/// indices and values are passed down once per entry instead of being
+/// re-read from wherever the caller's loop keeps them, which matters once
+/// the corpus is large enough that cache residency of the query is the
+/// whole game.
+pub fn sparse_dot_product_batch(
+    query_indices: &[u16],
+    query_values: &[f32],
+    corpus: &[(&[u16], &[f32])],
+) -> Vec<(u64, f64)> {
+    batch_dot_product(query_indices, query_values, corpus, sparse_dot_product)
+}
+
+/// [`sparse_dot_product_batch`], split across [`std::thread::available_parallelism`]
+/// threads by corpus chunk.
+///
+/// Worth reaching for once the corpus is large enough that the thread
+/// spawn/join overhead is noise next to the merge work it parallelizes;
+/// for small corpora, prefer [`sparse_dot_product_batch`].
+pub fn sparse_dot_product_batch_threaded(
+    query_indices: &[u16],
+    query_values: &[f32],
+    corpus: &[(&[u16], &[f32])],
+) -> Vec<(u64, f64)> {
+    batch_dot_product_threaded(query_indices, query_values, corpus, sparse_dot_product)
+}
+
+/// `u32`-indexed counterpart of [`sparse_dot_product_batch`].
+pub fn sparse_dot_product_batch_u32(
+    query_indices: &[u32],
+    query_values: &[f32],
+    corpus: &[(&[u32], &[f32])],
+) -> Vec<(u64, f64)> {
+    batch_dot_product(query_indices, query_values, corpus, sparse_dot_product_u32)
+}
+
+/// `u32`-indexed counterpart of [`sparse_dot_product_batch_threaded`].
+pub fn sparse_dot_product_batch_threaded_u32(
+    query_indices: &[u32],
+    query_values: &[f32],
+    corpus: &[(&[u32], &[f32])],
+) -> Vec<(u64, f64)> {
+    batch_dot_product_threaded(query_indices, query_values, corpus, sparse_dot_product_u32)
+}
+
+/// `u64`-indexed counterpart of [`sparse_dot_product_batch`].
+pub fn sparse_dot_product_batch_u64(
+    query_indices: &[u64],
+    query_values: &[f32],
+    corpus: &[(&[u64], &[f32])],
+) -> Vec<(u64, f64)> {
+    batch_dot_product(query_indices, query_values, corpus, sparse_dot_product_u64)
+}
+
+/// `u64`-indexed counterpart of [`sparse_dot_product_batch_threaded`].
+pub fn sparse_dot_product_batch_threaded_u64(
+    query_indices: &[u64],
+    query_values: &[f32],
+    corpus: &[(&[u64], &[f32])],
+) -> Vec<(u64, f64)> {
+    batch_dot_product_threaded(query_indices, query_values, corpus, sparse_dot_product_u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic xorshift64 generator, seeded per call site, so
+    /// property tests don't depend on `rand` being a dependency of the
+    /// library itself.
+    fn xorshift64(seed: u64) -> impl FnMut() -> u64 {
+        let mut state = seed;
+        move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        }
+    }
+
+    #[test]
+    fn sparse_jaccard_u64_matches_brute_force_match_count() {
+        let indices_a: Vec<u64> = vec![1, 3, 5, 7, 9, 11];
+        let indices_b: Vec<u64> = vec![0, 3, 4, 7, 10, 11];
+
+        let expected = jaccard_from_matches(
+            indices_a.len(),
+            indices_b.len(),
+            merge_match_count(&indices_a, &indices_b),
+        );
+        assert_eq!(sparse_jaccard_u64(&indices_a, &indices_b), expected);
+    }
+
+    #[test]
+    fn sparse_jaccard_u64_dispatches_through_gallop_for_lopsided_pairs() {
+        let long_indices: Vec<u64> = (0..200).map(|i| i * 3).collect();
+        let short_indices: Vec<u64> = vec![6, 15, 9_001];
+
+        let expected = merge_match_count(&long_indices, &short_indices);
+        assert_eq!(matches_u64(&long_indices, &short_indices), expected);
+        assert_eq!(
+            sparse_jaccard_u64(&long_indices, &short_indices),
+            jaccard_from_matches(long_indices.len(), short_indices.len(), expected)
+        );
+    }
+
+    #[test]
+    fn sparse_cosine_matches_brute_force_identity() {
+        let indices_a: Vec<u16> = vec![1, 3, 5, 7];
+        let values_a: Vec<f32> = vec![1.0, 2.0, 3.0, 4.0];
+        let indices_b: Vec<u16> = vec![0, 3, 5, 8];
+        let values_b: Vec<f32> = vec![5.0, 6.0, 7.0, 8.0];
+
+        let (_, dot) = merge_dot_product(&indices_a, &indices_b, &values_a, &values_b);
+        let norm_a = values_a
+            .iter()
+            .map(|&v| f64::from(v) * f64::from(v))
+            .sum::<f64>()
+            .sqrt();
+        let norm_b = values_b
+            .iter()
+            .map(|&v| f64::from(v) * f64::from(v))
+            .sum::<f64>()
+            .sqrt();
+        let expected = dot / (norm_a * norm_b);
+
+        let actual = sparse_cosine(&indices_a, &indices_b, &values_a, &values_b);
+        assert!((actual - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn sparse_sqeuclidean_matches_brute_force_identity() {
+        let indices_a: Vec<u16> = vec![1, 3, 5, 7];
+        let values_a: Vec<f32> = vec![1.0, 2.0, 3.0, 4.0];
+        let indices_b: Vec<u16> = vec![0, 3, 5, 8];
+        let values_b: Vec<f32> = vec![5.0, 6.0, 7.0, 8.0];
+
+        let mut dense_a = [0f32; 9];
+        let mut dense_b = [0f32; 9];
+        for (&idx, &value) in indices_a.iter().zip(&values_a) {
+            dense_a[idx as usize] = value;
+        }
+        for (&idx, &value) in indices_b.iter().zip(&values_b) {
+            dense_b[idx as usize] = value;
+        }
+        let expected: f64 = dense_a
+            .iter()
+            .zip(&dense_b)
+            .map(|(&a, &b)| f64::from(a - b) * f64::from(a - b))
+            .sum();
+
+        let actual = sparse_sqeuclidean(&indices_a, &indices_b, &values_a, &values_b);
+        assert!((actual - expected).abs() < 1e-6);
+    }
+
+    #[test]
+    fn sparse_dot_product_u32_matches_merge_reference() {
+        let indices_a: Vec<u32> = vec![1, 3, 5, 7, 9, 11];
+        let values_a: Vec<f32> = vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+        let indices_b: Vec<u32> = vec![0, 3, 4, 7, 10, 11];
+        let values_b: Vec<f32> = vec![10.0, 20.0, 30.0, 40.0, 50.0, 60.0];
+
+        let expected = merge_dot_product(&indices_a, &indices_b, &values_a, &values_b);
+        let actual = sparse_dot_product_u32(&indices_a, &indices_b, &values_a, &values_b);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn sparse_dot_product_u64_matches_merge_reference() {
+        let indices_a: Vec<u64> = vec![1, 3, 5, 7, 9, 11];
+        let values_a: Vec<f32> = vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+        let indices_b: Vec<u64> = vec![0, 3, 4, 7, 10, 11];
+        let values_b: Vec<f32> = vec![10.0, 20.0, 30.0, 40.0, 50.0, 60.0];
+
+        let expected = merge_dot_product(&indices_a, &indices_b, &values_a, &values_b);
+        let actual = sparse_dot_product_u64(&indices_a, &indices_b, &values_a, &values_b);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn bitmap_dot_product_matches_merge_reference_on_dense_small_domain() {
+        let indices_a: Vec<u16> = vec![1, 2, 4, 8, 16, 32, 64, 100, 200];
+        let values_a: Vec<f32> = (0..indices_a.len()).map(|i| (i + 1) as f32).collect();
+        let indices_b: Vec<u16> = vec![0, 2, 4, 6, 8, 64, 128, 200];
+        let values_b: Vec<f32> = (0..indices_b.len()).map(|i| (i * 3 + 1) as f32).collect();
+
+        let expected = merge_dot_product(&indices_a, &indices_b, &values_a, &values_b);
+        let actual = bitmap_dot_product(&indices_a, &values_a, &indices_b, &values_b);
+        assert_eq!(actual.0, expected.0);
+        assert!((actual.1 - expected.1).abs() < 1e-6);
+    }
+
+    #[test]
+    fn domain_span_saturates_instead_of_overflowing_at_u64_max() {
+        assert_eq!(domain_span(&[u64::MAX], &[1u64]), u64::MAX);
+        assert_eq!(domain_span::<u64>(&[], &[]), 1);
+    }
+
+    #[test]
+    fn sparse_dot_product_u64_handles_an_index_at_u64_max() {
+        // Regression test: domain_span used to compute max + 1 without a
+        // saturating add, panicking (debug) or wrapping to 0 (release) for
+        // an index at u64::MAX, which every auto-dispatching u64 entry
+        // point can reach via should_bitmap's span check.
+        let indices_a: Vec<u64> = vec![u64::MAX];
+        let indices_b: Vec<u64> = vec![u64::MAX];
+        let values_a: Vec<f32> = vec![1.0];
+        let values_b: Vec<f32> = vec![2.0];
+
+        let (matches, dot) = sparse_dot_product_u64(&indices_a, &indices_b, &values_a, &values_b);
+        assert_eq!(matches, 1);
+        assert!((dot - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn bitmap_match_count_matches_merge_reference() {
+        let indices_a: Vec<u16> = vec![1, 2, 4, 8, 16, 32, 64, 100, 200];
+        let indices_b: Vec<u16> = vec![0, 2, 4, 6, 8, 64, 128, 200];
+
+        let expected = merge_match_count(&indices_a, &indices_b);
+        let actual = bitmap_match_count(&indices_a, &indices_b);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn bitmap_dot_product_matches_merge_reference_on_random_dense_domains() {
+        let mut next = xorshift64(0x9E3779B97F4A7C15);
+
+        for _ in 0..200 {
+            let span = 64 + (next() % 512) as u32;
+            let len_a = 1 + (next() % 40) as usize;
+            let len_b = 1 + (next() % 40) as usize;
+
+            let mut set_a: std::collections::BTreeSet<u16> = std::collections::BTreeSet::new();
+            while set_a.len() < len_a.min(span as usize) {
+                set_a.insert((next() % span as u64) as u16);
+            }
+            let mut set_b: std::collections::BTreeSet<u16> = std::collections::BTreeSet::new();
+            while set_b.len() < len_b.min(span as usize) {
+                set_b.insert((next() % span as u64) as u16);
+            }
+
+            let indices_a: Vec<u16> = set_a.into_iter().collect();
+            let indices_b: Vec<u16> = set_b.into_iter().collect();
+            let values_a: Vec<f32> = (0..indices_a.len()).map(|i| (i + 1) as f32).collect();
+            let values_b: Vec<f32> = (0..indices_b.len()).map(|i| (i + 1) as f32).collect();
+
+            let expected = merge_dot_product(&indices_a, &indices_b, &values_a, &values_b);
+            let actual = bitmap_dot_product(&indices_a, &values_a, &indices_b, &values_b);
+            assert_eq!(actual.0, expected.0);
+            assert!((actual.1 - expected.1).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn batch_threaded_matches_single_threaded_batch() {
+        let query_indices: Vec<u16> = vec![1, 3, 5, 7, 9, 11, 13];
+        let query_values: Vec<f32> = vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0];
+
+        let mut next = xorshift64(0xD1B54A32D192ED03);
+
+        let mut corpus_indices: Vec<Vec<u16>> = Vec::new();
+        let mut corpus_values: Vec<Vec<f32>> = Vec::new();
+        for _ in 0..64 {
+            let len = 1 + (next() % 20) as usize;
+            let mut set: std::collections::BTreeSet<u16> = std::collections::BTreeSet::new();
+            while set.len() < len {
+                set.insert((next() % 32) as u16);
+            }
+            let indices: Vec<u16> = set.into_iter().collect();
+            let values: Vec<f32> = (0..indices.len()).map(|i| (i + 1) as f32).collect();
+            corpus_indices.push(indices);
+            corpus_values.push(values);
+        }
+
+        let corpus: Vec<(&[u16], &[f32])> = corpus_indices
+            .iter()
+            .zip(&corpus_values)
+            .map(|(indices, values)| (indices.as_slice(), values.as_slice()))
+            .collect();
+
+        let single = sparse_dot_product_batch(&query_indices, &query_values, &corpus);
+        let threaded = sparse_dot_product_batch_threaded(&query_indices, &query_values, &corpus);
+
+        assert_eq!(single.len(), corpus.len());
+        assert_eq!(single, threaded);
+    }
+
+    #[test]
+    fn batch_matches_one_call_per_entry_reference() {
+        let query_indices: Vec<u16> = vec![2, 4, 6, 8];
+        let query_values: Vec<f32> = vec![1.5, 2.5, 3.5, 4.5];
+        let corpus_indices: Vec<Vec<u16>> = vec![vec![1, 4, 8], vec![4, 6], vec![9, 10], vec![]];
+        let corpus_values: Vec<Vec<f32>> =
+            vec![vec![1.0, 2.0, 3.0], vec![4.0, 5.0], vec![6.0, 7.0], vec![]];
+
+        let corpus: Vec<(&[u16], &[f32])> = corpus_indices
+            .iter()
+            .zip(&corpus_values)
+            .map(|(indices, values)| (indices.as_slice(), values.as_slice()))
+            .collect();
+
+        let expected: Vec<(u64, f64)> = corpus
+            .iter()
+            .map(|&(indices, values)| {
+                sparse_dot_product(&query_indices, indices, &query_values, values)
+            })
+            .collect();
+
+        let batch = sparse_dot_product_batch(&query_indices, &query_values, &corpus);
+        let threaded = sparse_dot_product_batch_threaded(&query_indices, &query_values, &corpus);
+
+        assert_eq!(batch, expected);
+        assert_eq!(threaded, expected);
+    }
+
+    #[test]
+    fn gallop_search_finds_match_at_the_doubling_boundary() {
+        // Regression test: `target` (position 9) sits just past the last
+        // in-range probe (position 8); the next probe (16) runs off the end,
+        // so the binary search must cover the window clipped at `len`, which
+        // an earlier off-by-one in the bracket excluded.
+        let long: Vec<u32> = vec![
+            76, 230, 408, 538, 729, 753, 992, 1157, 1169, 1268, 1287, 1400, 1500,
+        ];
+        assert_eq!(gallop_search(&long, 0, 1268u32), Ok(9));
+    }
+
+    #[test]
+    fn gallop_dot_product_matches_merge_reference_on_the_boundary_case() {
+        let indices_a: Vec<u32> = vec![
+            76, 230, 408, 538, 729, 753, 992, 1157, 1169, 1268, 1287, 1400, 1500,
+        ];
+        let values_a: Vec<f32> = (0..indices_a.len()).map(|i| (i + 1) as f32).collect();
+        let indices_b: Vec<u32> = vec![174, 1268];
+        let values_b: Vec<f32> = vec![2.0, 658.24];
+
+        let expected = merge_dot_product(&indices_a, &indices_b, &values_a, &values_b);
+        let actual = gallop_merge(&indices_a, &indices_b, &values_a, &values_b);
+        assert_eq!(actual.0, expected.0);
+        assert!((actual.1 - expected.1).abs() < 1e-6);
+    }
+
+    #[test]
+    fn gallop_dot_product_matches_merge_reference_on_random_lopsided_pairs() {
+        let mut next = xorshift64(0x2545F4914F6CDD1D);
+
+        for _ in 0..200 {
+            let long_len = 50 + (next() % 500) as usize;
+            let short_len = 1 + (next() % 8) as usize;
+
+            let mut long_set: std::collections::BTreeSet<u32> = std::collections::BTreeSet::new();
+            while long_set.len() < long_len {
+                long_set.insert((next() % 4096) as u32);
+            }
+            let long_indices: Vec<u32> = long_set.into_iter().collect();
+
+            let mut short_set: std::collections::BTreeSet<u32> = std::collections::BTreeSet::new();
+            while short_set.len() < short_len {
+                short_set.insert((next() % 4096) as u32);
+            }
+            let short_indices: Vec<u32> = short_set.into_iter().collect();
+
+            let long_values: Vec<f32> = (0..long_indices.len()).map(|i| (i + 1) as f32).collect();
+            let short_values: Vec<f32> = (0..short_indices.len()).map(|i| (i + 1) as f32).collect();
+
+            let expected =
+                merge_dot_product(&long_indices, &short_indices, &long_values, &short_values);
+            let actual = gallop_merge(&long_indices, &short_indices, &long_values, &short_values);
+            assert_eq!(actual.0, expected.0);
+            assert!((actual.1 - expected.1).abs() < 1e-6);
+        }
+    }
+}